@@ -0,0 +1,134 @@
+use std::ffi::OsString;
+
+/// Flag prefix used for args that should be forwarded to every trigger type,
+/// e.g. `--all-quiet` -> `--quiet` on every spawned trigger.
+const COMMON_PREFIX: &str = "all";
+
+/// Routes the composite trigger's free-form trailing arguments to the
+/// trigger type(s) they're meant for.
+///
+/// Arguments are grouped by flag: a group is a `--flag` (or `--flag=value`)
+/// token together with any bare values that follow it, up to the next
+/// `--flag` token. A flag of the form `--<type>-<name>` is routed to the
+/// trigger type `<type>` with the prefix stripped down to `--<name>`; a
+/// flag of the form `--all-<name>` is routed to every trigger type the same
+/// way. Anything else is dropped silently - it isn't meant for us to route.
+pub struct ArgRouter {
+    groups: Vec<Vec<OsString>>,
+}
+
+impl ArgRouter {
+    pub fn new(args: &[OsString]) -> Self {
+        let mut groups: Vec<Vec<OsString>> = vec![];
+
+        for arg in args {
+            // Only a `--flag` token starts a new group. A single leading
+            // `-` is not enough - a value that itself looks like a
+            // negative number (e.g. `-5` in `--sqs-poll-interval -5`) must
+            // stay attached to the flag before it rather than being taken
+            // for a new, unrouted group.
+            if arg.to_string_lossy().starts_with("--") {
+                groups.push(vec![arg.clone()]);
+            } else if let Some(group) = groups.last_mut() {
+                group.push(arg.clone());
+            }
+            // A bare value with no preceding flag (e.g. a leading
+            // positional) has nothing to attach to, so it's ignored rather
+            // than panicking.
+        }
+
+        Self { groups }
+    }
+
+    /// Args that should be passed to every trigger type (the `--all-`
+    /// group), with the prefix stripped down to `--`.
+    pub fn common_args(&self) -> Vec<OsString> {
+        self.args_with_prefix(COMMON_PREFIX)
+    }
+
+    /// Args destined for one specific trigger type (the `--<trigger_type>-`
+    /// group), with its prefix stripped down to `--`.
+    pub fn args_for(&self, trigger_type: &str) -> Vec<OsString> {
+        self.args_with_prefix(trigger_type)
+    }
+
+    fn args_with_prefix(&self, prefix: &str) -> Vec<OsString> {
+        let flag_prefix = format!("--{prefix}-");
+
+        let mut out = vec![];
+
+        for group in &self.groups {
+            let (flag, inline_value) = split_flag_value(&group[0]);
+            let Some(rest) = flag.strip_prefix(&flag_prefix) else {
+                continue;
+            };
+
+            let mut new_group = group.clone();
+            new_group[0] = match inline_value {
+                Some(value) => format!("--{rest}={value}").into(),
+                None => format!("--{rest}").into(),
+            };
+            out.extend(new_group);
+        }
+
+        out
+    }
+}
+
+/// Splits a `--flag=value` token into its flag and value, or just returns
+/// the flag if there's no `=value` suffix.
+fn split_flag_value(arg: &OsString) -> (String, Option<String>) {
+    let text = arg.to_string_lossy();
+    match text.split_once('=') {
+        Some((flag, value)) => (flag.to_owned(), Some(value.to_owned())),
+        None => (text.into_owned(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(strs: &[&str]) -> Vec<OsString> {
+        strs.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn routes_type_prefixed_flag_with_separate_value() {
+        let router = ArgRouter::new(&args(&["--http-listen", "127.0.0.1:3000"]));
+        assert_eq!(router.args_for("http"), args(&["--listen", "127.0.0.1:3000"]));
+        assert!(router.args_for("sqs").is_empty());
+    }
+
+    #[test]
+    fn routes_type_prefixed_flag_with_inline_value() {
+        let router = ArgRouter::new(&args(&["--sqs-poll-interval=5"]));
+        assert_eq!(router.args_for("sqs"), args(&["--poll-interval=5"]));
+    }
+
+    #[test]
+    fn tolerates_leading_bare_positional() {
+        let router = ArgRouter::new(&args(&["spin.toml", "--http-listen", "127.0.0.1:3000"]));
+        assert_eq!(router.args_for("http"), args(&["--listen", "127.0.0.1:3000"]));
+    }
+
+    #[test]
+    fn drops_flags_prefixed_for_other_types() {
+        let router = ArgRouter::new(&args(&["--redis-quiet", "--http-listen", "127.0.0.1:3000"]));
+        assert!(router.args_for("sqs").is_empty());
+        assert_eq!(router.args_for("redis"), args(&["--quiet"]));
+    }
+
+    #[test]
+    fn common_args_are_routed_to_every_type() {
+        let router = ArgRouter::new(&args(&["--all-quiet", "--http-listen", "127.0.0.1:3000"]));
+        assert_eq!(router.common_args(), args(&["--quiet"]));
+        assert_eq!(router.args_for("http"), args(&["--listen", "127.0.0.1:3000"]));
+    }
+
+    #[test]
+    fn keeps_a_dash_leading_value_attached_to_its_flag() {
+        let router = ArgRouter::new(&args(&["--sqs-poll-interval", "-5"]));
+        assert_eq!(router.args_for("sqs"), args(&["--poll-interval", "-5"]));
+    }
+}
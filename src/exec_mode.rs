@@ -0,0 +1,13 @@
+/// How trigger types actually get run once their per-type locked sub-apps
+/// have been written out.
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum ExecMode {
+    /// Shell out to `spin trigger-<type>` per type (the original behavior).
+    /// Requires `spin` on PATH and re-loads each lockfile from disk.
+    #[default]
+    Subprocess,
+    /// Construct and run trigger executors directly in this process, on
+    /// this process's Tokio runtime, instead of spawning `spin` once per
+    /// trigger type.
+    InProcess,
+}
@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use spin_trigger::{loader::TriggerLoader, TriggerExecutor, TriggerExecutorBuilder};
+use spin_trigger_http::HttpTrigger;
+use spin_trigger_redis::RedisTrigger;
+use tokio::task::JoinSet;
+
+use crate::supervisor::TriggerSpec;
+
+/// Drives every trigger type directly on this process's Tokio runtime,
+/// instead of shelling out to `spin trigger-<type>`. This avoids spawning N
+/// child processes and re-reading each per-type lockfile through a
+/// subprocess, at the cost of only supporting the trigger types this binary
+/// is linked against an executor for.
+///
+/// Each trigger type is loaded through `TriggerLoader` and run concurrently;
+/// if any of them exits with an error, that error is propagated and the
+/// remaining triggers are aborted (much as the subprocess supervisor tears
+/// the rest of the app down when a trigger fails, minus the restart and
+/// graceful-shutdown policy, which only makes sense for separate processes).
+///
+/// Per-trigger CLI overrides (e.g. `--http-listen`) and `-e/--env` values
+/// have nowhere to go in this backend yet - there's no `RunConfig`/env
+/// plumbing into the trigger executors here - so rather than silently
+/// behaving differently from the subprocess backend, this rejects them
+/// with a clear error telling the user to fall back to `--exec-mode
+/// subprocess`.
+pub async fn run(specs: Vec<TriggerSpec>, working_dir: &Path, common_env: &[(String, String)]) -> Result<()> {
+    if !common_env.is_empty() {
+        bail!(
+            "--exec-mode in-process does not support -e/--env yet; \
+             rerun with --exec-mode subprocess"
+        );
+    }
+
+    let mut executors = JoinSet::new();
+
+    for spec in specs {
+        let working_dir = working_dir.to_owned();
+        executors.spawn(async move {
+            let trigger_type = spec.trigger_type.clone();
+            (trigger_type, run_one(spec, &working_dir).await)
+        });
+    }
+
+    while let Some(joined) = executors.join_next().await {
+        let (trigger_type, result) = joined.context("in-process trigger task panicked")?;
+        result.with_context(|| format!("the {trigger_type} trigger exited with an error"))?;
+    }
+
+    Ok(())
+}
+
+async fn run_one(spec: TriggerSpec, working_dir: &Path) -> Result<()> {
+    if !spec.args.is_empty() {
+        bail!(
+            "--exec-mode in-process does not support per-trigger CLI overrides yet \
+             (the {} trigger was given {:?}); rerun with --exec-mode subprocess",
+            spec.trigger_type,
+            spec.args,
+        );
+    }
+
+    match spec.trigger_type.as_str() {
+        "http" => run_executor::<HttpTrigger>(&spec, working_dir).await,
+        "redis" => run_executor::<RedisTrigger>(&spec, working_dir).await,
+        other => bail!(
+            "--exec-mode in-process does not support the '{other}' trigger type yet; \
+             rerun with --exec-mode subprocess"
+        ),
+    }
+}
+
+async fn run_executor<T: TriggerExecutor>(spec: &TriggerSpec, working_dir: &Path) -> Result<()>
+where
+    T::RuntimeConfig: Default,
+{
+    let loader = TriggerLoader::new(working_dir, false);
+    let executor: T = TriggerExecutorBuilder::new(loader)
+        .build(spec.lockfile_url.clone(), Default::default())
+        .await
+        .with_context(|| format!("failed to build the {} trigger executor", spec.trigger_type))?;
+
+    executor
+        .run(Default::default())
+        .await
+        .with_context(|| format!("failed to run the {} trigger", spec.trigger_type))
+}
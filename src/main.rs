@@ -1,18 +1,74 @@
-use std::{path::{PathBuf, Path}, collections::{HashSet}};
+use std::{path::{PathBuf, Path}, collections::{HashSet}, time::Duration};
 
 use anyhow::{anyhow, Error, Result, Context};
 use clap::{Parser};
 use spin_app::{Loader, locked::{LockedApp, LockedTrigger}};
 use url::Url;
 
+use args::ArgRouter;
+use exec_mode::ExecMode;
+use supervisor::{RestartPolicy, Supervisor, TriggerSpec};
+
+mod args;
+mod exec_mode;
+mod in_process;
+mod stdio;
+mod supervisor;
+
 #[derive(Debug, clap::Parser)]
 #[clap(
     allow_hyphen_values = true,
 )]
 struct CompositeApp {
+    /// Restrict execution to the given component(s). May be repeated.
+    /// Any trigger whose component is not in this set is not launched.
+    #[clap(long = "component")]
+    component: Vec<String>,
+
+    /// Tag each line of child trigger output with a colored `[<trigger_type>]`
+    /// prefix. On by default; pass --no-log-prefix for raw, unprefixed output
+    /// (e.g. when a machine is consuming the combined stream).
+    #[clap(long = "log-prefix", default_value_t = true, overrides_with = "no_log_prefix")]
+    log_prefix: bool,
+
+    #[clap(long = "no-log-prefix", overrides_with = "log_prefix")]
+    no_log_prefix: bool,
+
+    /// Relaunch a trigger subprocess if it exits with a failure status,
+    /// instead of tearing down the whole composite app.
+    #[clap(long = "restart-on-failure")]
+    restart_on_failure: bool,
+
+    /// With --restart-on-failure, the maximum number of times to relaunch a
+    /// given trigger before giving up on it. Unlimited if not set.
+    #[clap(long = "max-restarts", requires = "restart_on_failure")]
+    max_restarts: Option<u32>,
+
+    /// How long to give child triggers to shut down after ctrl-c before
+    /// they are force-killed.
+    #[clap(long = "shutdown-grace-period-secs", default_value_t = 10)]
+    shutdown_grace_period_secs: u64,
+
+    /// Set an environment variable (`key=value`) in every spawned trigger
+    /// subprocess. May be repeated.
+    #[clap(short = 'e', long = "env", value_parser = parse_env_kv)]
+    env: Vec<(String, String)>,
+
+    /// How to run each trigger type: as a `spin trigger-<type>` subprocess
+    /// (the default), or in-process on this binary's own Tokio runtime.
+    #[clap(long = "exec-mode", value_enum, default_value_t = ExecMode::Subprocess)]
+    exec_mode: ExecMode,
+
     args: Vec<std::ffi::OsString>,
 }
 
+fn parse_env_kv(s: &str) -> Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("environment variable '{s}' must be in the form key=value"))?;
+    Ok((key.to_owned(), value.to_owned()))
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     let t = CompositeApp::parse();
@@ -21,10 +77,6 @@ async fn main() -> Result<(), Error> {
 
 impl CompositeApp {
     async fn run(&self) -> anyhow::Result<()> {
-        let ctrlc = tokio::spawn(async move {
-            tokio::signal::ctrl_c().await.unwrap();
-        });
-        
         // We are going to need to read the file at $SPIN_LOCKED_URL,
         // transform it into a file per trigger, and pass that on to
         // `spin trigger ...` or `spin trigger-...`
@@ -37,84 +89,96 @@ impl CompositeApp {
         let loader = spin_trigger::loader::TriggerLoader::new(&working_dir, false);
         let app = loader.load_app(&lockfile_url).await?;
 
+        let component_filter = self.component.iter().cloned().collect::<HashSet<_>>();
+        self.check_component_filter(&app, &component_filter)?;
+
         let trigger_types = app
             .triggers
             .iter()
             .filter_map(|t| t.trigger_config.get("type").and_then(|v| v.as_str().map(|vv| vv.to_owned())))
             .collect::<HashSet<_>>();
 
-        let mut triggers = tokio::task::JoinSet::new();
+        let mut specs = vec![];
 
         for trigger_type in trigger_types {
-            let subapp = locked_app_for_trigger(&app, &trigger_type);
+            let subapp = locked_app_for_trigger(&app, &trigger_type, &component_filter);
+            if subapp.triggers.is_empty() {
+                // Every trigger of this type was filtered out by --component:
+                // don't spawn a subprocess that would have nothing to run.
+                continue;
+            }
             let args = self.args_for_trigger(&trigger_type);
-            let lockfile2 = write_locked_app(&subapp, &trigger_type, &working_dir).await?;
-            let trigger_subcommand = if trigger_type == "http" || trigger_type == "redis" {
+            let trigger_lockfile_url = write_locked_app(&subapp, &trigger_type, &working_dir).await?;
+            let subcommand = if trigger_type == "http" || trigger_type == "redis" {
                 vec!["trigger".to_owned(), trigger_type.to_owned()]
             } else {
                 vec![format!("trigger-{trigger_type}")]
             };
-            triggers.spawn(async move {
-                let mut child = tokio::process::Command::new("spin")
-                    .args(trigger_subcommand)
-                    .args(args)
-                    .env("SPIN_LOCKED_URL", &lockfile2)
-                    .spawn()
-                    .unwrap();
-                child.wait().await
-            });
+
+            specs.push(TriggerSpec { trigger_type, subcommand, args, lockfile_url: trigger_lockfile_url });
         }
 
-        tokio::select! {
-            _ = ctrlc => {
-                triggers.abort_all()
-            },
-            _ = triggers.join_next() => {
-                triggers.abort_all()
+        match self.exec_mode {
+            ExecMode::Subprocess => {
+                let supervisor = Supervisor::new(
+                    self.shutdown_grace_period(),
+                    self.restart_policy(),
+                    self.use_log_prefix(),
+                    self.env.clone(),
+                );
+                supervisor.run(specs).await
             }
-        };
+            ExecMode::InProcess => in_process::run(specs, &working_dir, &self.env).await,
+        }
+    }
 
-        Ok(())
+    fn restart_policy(&self) -> RestartPolicy {
+        RestartPolicy {
+            enabled: self.restart_on_failure,
+            max_restarts: self.max_restarts,
+        }
     }
 
-    fn args_for_trigger(&self, trigger_type: &str) -> Vec<std::ffi::OsString> {
-        let grupps = self.gruppified_args();
-        let tt_prefix = format!("--{trigger_type}-");
+    fn shutdown_grace_period(&self) -> Duration {
+        Duration::from_secs(self.shutdown_grace_period_secs)
+    }
 
-        let mut args = vec![];
+    fn use_log_prefix(&self) -> bool {
+        self.log_prefix && !self.no_log_prefix
+    }
 
-        for mut grupp in grupps {
-            let opt_text = grupp[0].to_string_lossy();
-            if opt_text.starts_with(&tt_prefix) {
-                let new_opt_text = opt_text.replace(&tt_prefix, "--");
-                grupp[0] = new_opt_text.into();
-                args.extend(grupp.into_iter());
-            }
-            // otherwise we skip this grupp
-            // TODO: friendlier handling for common args like --quiet - currently these would need to be e.g. --http-quiet --sqs-quiet
+    /// If a `--component` filter was supplied, check that every requested id
+    /// actually names a component with a trigger in this app, so that a typo
+    /// fails fast instead of silently launching nothing.
+    fn check_component_filter(&self, app: &LockedApp, component_filter: &HashSet<String>) -> Result<()> {
+        if component_filter.is_empty() {
+            return Ok(());
         }
 
-        args
-    }
-
-    fn gruppified_args(&self) -> Vec<Vec<std::ffi::OsString>> {
-        // they're not groups but I have no idea what they are
-        let mut grupps = vec![];
+        let known_ids = app
+            .triggers
+            .iter()
+            .map(trigger_component_id)
+            .collect::<HashSet<_>>();
 
-        for arg in &self.args {
-            if arg.to_string_lossy().starts_with('-') {
-                // We are beginning a new grupp
-                grupps.push(vec![]);
+        for id in component_filter {
+            if !known_ids.contains(id.as_str()) {
+                return Err(anyhow!("no component '{id}' found in this application"));
             }
-            // TODO: this will do terrible things if the first arg is not hyphened
-            grupps.last_mut().unwrap().push(arg.clone());
         }
 
-        grupps
+        Ok(())
+    }
+
+    fn args_for_trigger(&self, trigger_type: &str) -> Vec<std::ffi::OsString> {
+        let router = ArgRouter::new(&self.args);
+        let mut args = router.common_args();
+        args.extend(router.args_for(trigger_type));
+        args
     }
 }
 
-fn locked_app_for_trigger(app: &LockedApp, trigger_type: &str) -> LockedApp {
+fn locked_app_for_trigger(app: &LockedApp, trigger_type: &str, component_filter: &HashSet<String>) -> LockedApp {
     let mut subset = app.clone();
 
     // Restrict set of trigger-components
@@ -122,6 +186,7 @@ fn locked_app_for_trigger(app: &LockedApp, trigger_type: &str) -> LockedApp {
         .triggers
         .into_iter()
         .filter(|t| t.trigger_config.get("type").and_then(|v| v.as_str()) == Some(trigger_type))
+        .filter(|t| component_filter.is_empty() || component_filter.contains(trigger_component_id(t)))
         .map(|t| uncompositify(t))
         .collect();
 
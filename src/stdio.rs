@@ -0,0 +1,66 @@
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::task::JoinHandle;
+
+/// ANSI colours for the trigger-type prefixes. Each type's colour is picked
+/// from its name, so a type always gets the same colour; two types can land
+/// on the same colour (e.g. `redis` and `sqs`), in which case only the tag
+/// text tells them apart. Picked to read reasonably on both light and dark
+/// terminal backgrounds.
+const COLORS: &[&str] = &["36", "35", "33", "32", "34", "31"];
+
+/// The stdout and stderr line-reader tasks spawned for a single child. The
+/// child exiting and its pipes hitting EOF are separate kernel events, so a
+/// caller that wants to be sure all of a child's output has actually been
+/// drained before reporting its exit (or tearing down) needs to await these
+/// alongside `Child::wait`.
+pub type IoHandles = (JoinHandle<()>, JoinHandle<()>);
+
+/// Wires up a spawned trigger subprocess's stdout/stderr so each line is
+/// re-emitted on the parent's stdout/stderr, optionally tagged with a
+/// colored `[<trigger_type>]` prefix. This keeps interleaved output from
+/// multiple trigger types distinguishable instead of merging into one
+/// undifferentiated stream.
+///
+/// The child must have been spawned with `.stdout(Stdio::piped())` and
+/// `.stderr(Stdio::piped())`. Returns the reader tasks' handles so the
+/// caller can await them before treating the child's output as complete.
+pub fn forward_child_output(child: &mut tokio::process::Child, trigger_type: &str, use_prefix: bool) -> IoHandles {
+    let stdout = child.stdout.take().expect("child stdout was not piped");
+    let stderr = child.stderr.take().expect("child stderr was not piped");
+
+    let prefix = use_prefix.then(|| colored_prefix(trigger_type));
+
+    let stdout_task = tokio::spawn(forward_lines(stdout, prefix.clone(), false));
+    let stderr_task = tokio::spawn(forward_lines(stderr, prefix, true));
+    (stdout_task, stderr_task)
+}
+
+/// Awaits a child's reader tasks so its output is fully drained before the
+/// caller moves on (e.g. to report its exit status or tear down).
+pub async fn drain(io_handles: IoHandles) {
+    let (stdout_task, stderr_task) = io_handles;
+    let _ = stdout_task.await;
+    let _ = stderr_task.await;
+}
+
+fn colored_prefix(trigger_type: &str) -> String {
+    let color = color_for(trigger_type);
+    format!("\x1b[{color}m[{trigger_type}]\x1b[0m ")
+}
+
+fn color_for(trigger_type: &str) -> &'static str {
+    let idx = trigger_type.bytes().map(|b| b as usize).sum::<usize>() % COLORS.len();
+    COLORS[idx]
+}
+
+async fn forward_lines(reader: impl AsyncRead + Unpin, prefix: Option<String>, to_stderr: bool) {
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        match (&prefix, to_stderr) {
+            (Some(p), false) => println!("{p}{line}"),
+            (Some(p), true) => eprintln!("{p}{line}"),
+            (None, false) => println!("{line}"),
+            (None, true) => eprintln!("{line}"),
+        }
+    }
+}
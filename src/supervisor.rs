@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::process::{ExitStatus, Stdio};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use tokio::process::Command;
+use tokio::task::JoinSet;
+
+use crate::stdio::{self, IoHandles};
+
+/// Everything needed to (re)spawn a single trigger-type subprocess, so a
+/// crashed trigger can be relaunched without redoing the work of loading
+/// and splitting the locked app.
+#[derive(Clone)]
+pub struct TriggerSpec {
+    pub trigger_type: String,
+    pub subcommand: Vec<String>,
+    pub args: Vec<std::ffi::OsString>,
+    pub lockfile_url: String,
+}
+
+/// Whether to relaunch a trigger subprocess that exits with a failure
+/// status, and if so, how many times before giving up on it.
+#[derive(Clone, Copy)]
+pub struct RestartPolicy {
+    pub enabled: bool,
+    pub max_restarts: Option<u32>,
+}
+
+impl RestartPolicy {
+    fn allows(&self, restarts_so_far: u32) -> bool {
+        self.enabled && self.max_restarts.map_or(true, |max| restarts_so_far < max)
+    }
+}
+
+struct RunningTrigger {
+    spec: TriggerSpec,
+    pid: u32,
+    restarts: u32,
+    io_handles: IoHandles,
+}
+
+/// Supervises a set of trigger subprocesses: starts them all, reports each
+/// one's exit, applies the restart policy to failures, and on ctrl-c gives
+/// every child a chance to shut down gracefully before forcing the issue.
+pub struct Supervisor {
+    grace_period: Duration,
+    restart_policy: RestartPolicy,
+    use_log_prefix: bool,
+    common_env: Vec<(String, String)>,
+    running: HashMap<String, RunningTrigger>,
+    waits: JoinSet<(String, std::io::Result<ExitStatus>)>,
+}
+
+impl Supervisor {
+    pub fn new(
+        grace_period: Duration,
+        restart_policy: RestartPolicy,
+        use_log_prefix: bool,
+        common_env: Vec<(String, String)>,
+    ) -> Self {
+        Self {
+            grace_period,
+            restart_policy,
+            use_log_prefix,
+            common_env,
+            running: HashMap::new(),
+            waits: JoinSet::new(),
+        }
+    }
+
+    /// Spawns every trigger and then runs until either all of them have
+    /// exited (and none are left to restart) or the user hits ctrl-c.
+    ///
+    /// Any failure along the way - a trigger failing to spawn, a restart
+    /// failing to spawn, or a wait-task panicking - shuts down whatever
+    /// siblings are still running before the error is returned, so a
+    /// failure never leaves orphaned trigger processes behind.
+    pub async fn run(mut self, specs: Vec<TriggerSpec>) -> Result<()> {
+        for spec in specs {
+            if let Err(err) = self.spawn(spec, 0) {
+                self.shutdown().await;
+                return Err(err);
+            }
+        }
+
+        loop {
+            if self.running.is_empty() {
+                return Ok(());
+            }
+
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {
+                    self.shutdown().await;
+                    return Ok(());
+                }
+                Some(joined) = self.waits.join_next() => {
+                    let (trigger_type, wait_result) = match joined {
+                        Ok(pair) => pair,
+                        Err(err) => {
+                            self.shutdown().await;
+                            return Err(anyhow::Error::new(err).context("trigger supervisor task panicked"));
+                        }
+                    };
+                    let Some(running) = self.running.remove(&trigger_type) else {
+                        continue;
+                    };
+                    let status = match wait_result {
+                        Ok(status) => status,
+                        Err(err) => {
+                            stdio::drain(running.io_handles).await;
+                            self.shutdown().await;
+                            return Err(anyhow::Error::new(err)
+                                .context(format!("failed to wait on the {trigger_type} trigger")));
+                        }
+                    };
+
+                    stdio::drain(running.io_handles).await;
+                    report_exit(&trigger_type, &status);
+
+                    if !status.success() {
+                        if self.restart_policy.allows(running.restarts) {
+                            eprintln!("[{trigger_type}] restarting (attempt {})", running.restarts + 1);
+                            if let Err(err) = self.spawn(running.spec, running.restarts + 1) {
+                                self.shutdown().await;
+                                return Err(err);
+                            }
+                        } else {
+                            // Not restarting - this trigger is down for good, so
+                            // there's no point keeping its siblings up. Shut the
+                            // rest of the app down gracefully and fail loudly
+                            // rather than silently running degraded forever.
+                            self.shutdown().await;
+                            return Err(anyhow!(
+                                "the {trigger_type} trigger exited with {status} and will not be restarted"
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn spawn(&mut self, spec: TriggerSpec, restarts: u32) -> Result<()> {
+        let mut child = Command::new("spin")
+            .args(spec.subcommand.clone())
+            .args(spec.args.clone())
+            .env("SPIN_LOCKED_URL", &spec.lockfile_url)
+            .envs(self.common_env.iter().map(|(k, v)| (k, v)))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to start the {} trigger", spec.trigger_type))?;
+
+        let pid = child.id().context("trigger process exited before it could be supervised")?;
+        let io_handles = stdio::forward_child_output(&mut child, &spec.trigger_type, self.use_log_prefix);
+
+        let trigger_type = spec.trigger_type.clone();
+        self.waits.spawn(async move { (trigger_type, child.wait().await) });
+        self.running.insert(spec.trigger_type.clone(), RunningTrigger { spec, pid, restarts, io_handles });
+
+        Ok(())
+    }
+
+    /// Asks every remaining child to terminate, gives them `grace_period` to
+    /// do so cleanly, then kills whatever is left. Each child's output is
+    /// drained before it's reported, and before this returns, so nothing
+    /// trails off mid-line when the runtime tears down afterwards.
+    async fn shutdown(&mut self) {
+        for running in self.running.values() {
+            send_signal(running.pid, Signal::SIGTERM);
+        }
+
+        let deadline = tokio::time::sleep(self.grace_period);
+        tokio::pin!(deadline);
+
+        loop {
+            if self.running.is_empty() {
+                return;
+            }
+            tokio::select! {
+                _ = &mut deadline => break,
+                Some(joined) = self.waits.join_next() => {
+                    if let Ok((trigger_type, wait_result)) = joined {
+                        if let Some(running) = self.running.remove(&trigger_type) {
+                            stdio::drain(running.io_handles).await;
+                            if let Ok(status) = wait_result {
+                                report_exit(&trigger_type, &status);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let stragglers = self.running.drain().map(|(_, running)| running).collect::<Vec<_>>();
+        for running in &stragglers {
+            eprintln!("[{}] did not exit within the grace period, killing it", running.spec.trigger_type);
+            send_signal(running.pid, Signal::SIGKILL);
+        }
+        for running in stragglers {
+            stdio::drain(running.io_handles).await;
+        }
+    }
+}
+
+fn send_signal(pid: u32, signal: Signal) {
+    let _ = signal::kill(Pid::from_raw(pid as i32), signal);
+}
+
+fn report_exit(trigger_type: &str, status: &ExitStatus) {
+    match status.code() {
+        Some(0) => println!("[{trigger_type}] exited successfully"),
+        Some(code) => eprintln!("[{trigger_type}] exited with status code {code}"),
+        None => eprintln!("[{trigger_type}] terminated by signal"),
+    }
+}